@@ -1,8 +1,16 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use postgres::types::ToSql;
 use postgres::Error as PostgresError;
 use postgres::{Client, NoTls};
+use r2d2_postgres::PostgresConnectionManager;
+use sha2::Sha256;
+use std::collections::HashMap;
 use std::env;
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[macro_use]
 extern crate serde_derive;
@@ -14,15 +22,54 @@ struct User {
     name: String,
     email: String,
     password: String,
+    #[serde(default = "default_attributes")]
+    attributes: serde_json::Value,
+}
+
+fn default_attributes() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+// Sanitized view of a User returned to clients, without the password hash
+#[derive(Serialize)]
+struct UserInformation {
+    id: Option<i32>,
+    name: String,
+    email: String,
+    attributes: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct LoginRequest {
+    email: String,
+    password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    sub: i32,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct RoleAssignment {
+    role_id: i32,
 }
 
 //DB Connection
 const DB_URL: &str = env!("DATABASE_URL");
 
+type DbPool = r2d2::Pool<PostgresConnectionManager<NoTls>>;
+
 // Constants Headers
 const OK_RESPONSE: &str = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n";
 const NOT_FOUND: &str = "HTTP/1.1 404 NOT FOUND\r\n";
 const INTERNAL_SERVER_ERROR: &str = "HTTP/1.1 500 INTERNAL SERVER ERROR\r\n";
+const UNAUTHORIZED: &str = "HTTP/1.1 401 UNAUTHORIZED\r\n";
+const FORBIDDEN: &str = "HTTP/1.1 403 FORBIDDEN\r\n";
+const BAD_REQUEST: &str = "HTTP/1.1 400 BAD REQUEST\r\nContent-Type: application/json\r\n\r\n";
+
+const TOKEN_TTL_SECS: i64 = 3600;
 
 //main function
 fn main() {
@@ -32,6 +79,15 @@ fn main() {
         return;
     }
 
+    // Build the connection pool
+    let pool = match build_pool() {
+        Ok(pool) => Arc::new(pool),
+        Err(e) => {
+            println!("Error building connection pool: {}", e);
+            return;
+        }
+    };
+
     // Start the server
     let listener = TcpListener::bind("0.0.0.0:8080").unwrap();
     println!("Server started at port 8080");
@@ -40,7 +96,7 @@ fn main() {
     for stream in listener.incoming() {
         match stream {
             Ok(mut stream) => {
-                handle_client(&mut stream);
+                handle_client(&mut stream, &pool);
             }
             Err(e) => {
                 println!("Error: {}", e);
@@ -49,7 +105,25 @@ fn main() {
     }
 }
 
-fn handle_client(mut stream: &TcpStream) {
+fn build_pool() -> Result<DbPool, r2d2::Error> {
+    let manager = PostgresConnectionManager::new(DB_URL.parse().unwrap(), NoTls);
+
+    let pool_size = env::var("DB_POOL_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10);
+    let connection_timeout_secs = env::var("DB_POOL_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+
+    r2d2::Pool::builder()
+        .max_size(pool_size)
+        .connection_timeout(Duration::from_secs(connection_timeout_secs))
+        .build(manager)
+}
+
+fn handle_client(mut stream: &TcpStream, pool: &DbPool) {
     // Read the request
     let mut buffer = [0; 1024];
     let mut request = String::new();
@@ -58,14 +132,10 @@ fn handle_client(mut stream: &TcpStream) {
         Ok(size) => {
             request.push_str(String::from_utf8_lossy(&buffer[..size]).as_ref());
 
-            // Handle the requests
-            let (status_line, content) = match &*request {
-                r if r.starts_with("GET /users") => handle_get_all_users_request(),
-                r if r.starts_with("GET /users/") => handle_get_request(r),
-                r if r.starts_with("POST /users") => handle_post_request(r),
-                r if r.starts_with("PUT /users/") => handle_put_request(r),
-                r if r.starts_with("DELETE /users/") => handle_delete_request(r),
-                _ => (NOT_FOUND, "Not Found".to_string()),
+            // Route the request
+            let (status_line, content) = match parse_request_line(&request) {
+                Some(parsed) => route(&parsed, &request, pool),
+                None => (NOT_FOUND.to_string(), "Not Found".to_string()),
             };
 
             // Send the response
@@ -79,6 +149,98 @@ fn handle_client(mut stream: &TcpStream) {
     }
 }
 
+struct ParsedRequest<'a> {
+    method: &'a str,
+    path: Vec<&'a str>,
+    query: HashMap<String, String>,
+}
+
+fn parse_request_line(request: &str) -> Option<ParsedRequest> {
+    let request_line = request.lines().next()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+    let path = path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+    Some(ParsedRequest {
+        method,
+        path,
+        query: parse_query_string(query_string),
+    })
+}
+
+// parse a "a=1&b=2" query string into a lookup of key to value
+fn parse_query_string(query_string: &str) -> HashMap<String, String> {
+    query_string
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (key.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+fn route(parsed: &ParsedRequest, request: &str, pool: &DbPool) -> (String, String) {
+    match (parsed.method, parsed.path.as_slice()) {
+        ("POST", ["login"]) => handle_login_request(request, pool),
+        ("GET", ["users"]) => match authenticate(request) {
+            Some(user_id) => {
+                handle_get_all_users_request(user_id, pool, &parse_list_query(&parsed.query))
+            }
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("GET", ["users", _, "permissions"]) => match authenticate(request) {
+            Some(user_id) => handle_get_user_permissions_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("POST", ["users", _, "roles"]) => match authenticate(request) {
+            Some(user_id) => handle_assign_role_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("DELETE", ["users", _, "roles"]) => match authenticate(request) {
+            Some(user_id) => handle_remove_role_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("GET", ["users", _]) => match authenticate(request) {
+            Some(user_id) => handle_get_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("POST", ["users"]) => match authenticate(request) {
+            Some(user_id) => handle_post_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("PUT", ["users", _]) => match authenticate(request) {
+            Some(user_id) => handle_put_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        ("DELETE", ["users", _]) => match authenticate(request) {
+            Some(user_id) => handle_delete_request(request, user_id, pool),
+            None => (UNAUTHORIZED.to_string(), "Unauthorized".to_string()),
+        },
+        _ => (NOT_FOUND.to_string(), "Not Found".to_string()),
+    }
+}
+
+// pagination/filtering options accepted by GET /users
+struct ListQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    email: Option<String>,
+    name: Option<String>,
+}
+
+fn parse_list_query(query: &HashMap<String, String>) -> ListQuery {
+    ListQuery {
+        limit: query.get("limit").and_then(|v| v.parse().ok()),
+        offset: query.get("offset").and_then(|v| v.parse().ok()),
+        email: query.get("email").cloned(),
+        name: query.get("name").cloned(),
+    }
+}
+
 fn set_databse() -> Result<(), PostgresError> {
     // Connect to the database
     let mut client = Client::connect(DB_URL, NoTls)?;
@@ -90,11 +252,103 @@ fn set_databse() -> Result<(), PostgresError> {
             id SERIAL PRIMARY KEY,
             name TEXT NOT NULL,
             email TEXT NOT NULL,
-            password TEXT NOT NULL
+            password TEXT NOT NULL,
+            attributes JSONB NOT NULL DEFAULT '{}'
         )
     ",
         &[],
     )?;
+
+    // Create the RBAC tables
+    client.execute(
+        "
+        CREATE TABLE IF NOT EXISTS roles (
+            id SERIAL PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        )
+    ",
+        &[],
+    )?;
+    client.execute(
+        "
+        CREATE TABLE IF NOT EXISTS permissions (
+            id SERIAL PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        )
+    ",
+        &[],
+    )?;
+    client.execute(
+        "
+        CREATE TABLE IF NOT EXISTS user_roles (
+            user_id INTEGER NOT NULL REFERENCES users(id),
+            role_id INTEGER NOT NULL REFERENCES roles(id),
+            PRIMARY KEY (user_id, role_id)
+        )
+    ",
+        &[],
+    )?;
+    client.execute(
+        "
+        CREATE TABLE IF NOT EXISTS role_permissions (
+            role_id INTEGER NOT NULL REFERENCES roles(id),
+            permission_id INTEGER NOT NULL REFERENCES permissions(id),
+            PRIMARY KEY (role_id, permission_id)
+        )
+    ",
+        &[],
+    )?;
+
+    // Seed the default ADMIN role and the base user-management permissions
+    client.execute(
+        "INSERT INTO roles (name) VALUES ('ADMIN') ON CONFLICT (name) DO NOTHING",
+        &[],
+    )?;
+    for permission in [
+        "CREATE_USER",
+        "DELETE_USER",
+        "VIEW_USER",
+        "UPDATE_USER",
+        "MANAGE_ROLES",
+    ] {
+        client.execute(
+            "INSERT INTO permissions (name) VALUES ($1) ON CONFLICT (name) DO NOTHING",
+            &[&permission],
+        )?;
+    }
+    client.execute(
+        "
+        INSERT INTO role_permissions (role_id, permission_id)
+        SELECT r.id, p.id FROM roles r, permissions p WHERE r.name = 'ADMIN'
+        ON CONFLICT DO NOTHING
+    ",
+        &[],
+    )?;
+
+    // Bootstrap the first ADMIN user from env vars, since every other path to
+    // creating a user or granting a role now requires a permission only an
+    // existing ADMIN can grant.
+    if let (Ok(email), Ok(password)) = (env::var("ADMIN_EMAIL"), env::var("ADMIN_PASSWORD")) {
+        let password_hash = bcrypt::hash(&password, bcrypt::DEFAULT_COST).unwrap();
+        client.execute(
+            "
+            INSERT INTO users (name, email, password)
+            SELECT 'Admin', $1, $2
+            WHERE NOT EXISTS (SELECT 1 FROM users WHERE email = $1)
+        ",
+            &[&email, &password_hash],
+        )?;
+        client.execute(
+            "
+            INSERT INTO user_roles (user_id, role_id)
+            SELECT u.id, r.id FROM users u, roles r
+            WHERE u.email = $1 AND r.name = 'ADMIN'
+            ON CONFLICT DO NOTHING
+        ",
+            &[&email],
+        )?;
+    }
+
     Ok(())
 }
 
@@ -115,18 +369,197 @@ fn get_user_request_body(request: &str) -> Result<User, serde_json::Error> {
     serde_json::from_str(request.split("\r\n\r\n").last().unwrap_or_default()) // {"name": "John", "email": "john@example", "password": "password"}
 }
 
+// Validation
+
+// parse the id out of the request path, yielding a 400 response on malformed input
+fn parse_id(request: &str) -> Result<i32, (String, String)> {
+    get_id(request)
+        .parse::<i32>()
+        .map_err(|_| bad_request("id must be an integer"))
+}
+
+fn bad_request(message: &str) -> (String, String) {
+    (
+        BAD_REQUEST.to_string(),
+        serde_json::to_string(&serde_json::json!({ "error": message })).unwrap(),
+    )
+}
+
+trait Check {
+    fn check(&self) -> Result<(), String>;
+}
+
+impl Check for User {
+    fn check(&self) -> Result<(), String> {
+        assert_length(&self.name, 1, 50, "name must be between 1 and 50 characters")?;
+        assert_length(
+            &self.email,
+            1,
+            100,
+            "email must be between 1 and 100 characters",
+        )?;
+        if !self.email.contains('@') {
+            return Err("email must contain '@'".to_string());
+        }
+        assert_length(&self.password, 1, usize::MAX, "password must not be empty")?;
+        Ok(())
+    }
+}
+
+fn assert_length(field: &str, min: usize, max: usize, msg: &str) -> Result<(), String> {
+    let length = field.chars().count();
+    if length < min || length > max {
+        Err(msg.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Auth
+
+fn jwt_secret() -> String {
+    env::var("JWT_SECRET").expect("JWT_SECRET must be set")
+}
+
+fn sign(data: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(jwt_secret().as_bytes()).unwrap();
+    mac.update(data.as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+fn create_token(user_id: i32) -> String {
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+        + TOKEN_TTL_SECS;
+    let claims = Claims { sub: user_id, exp };
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_string(&claims).unwrap());
+    let signing_input = format!("{}.{}", header, payload);
+    let signature = sign(&signing_input);
+    format!("{}.{}", signing_input, signature)
+}
+
+fn verify_token(token: &str) -> Option<Claims> {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let signing_input = format!("{}.{}", parts[0], parts[1]);
+    let signature = URL_SAFE_NO_PAD.decode(parts[2]).ok()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(jwt_secret().as_bytes()).ok()?;
+    mac.update(signing_input.as_bytes());
+    // constant-time comparison so a forged signature can't be brute-forced byte by byte
+    mac.verify_slice(&signature).ok()?;
+
+    let payload = URL_SAFE_NO_PAD.decode(parts[1]).ok()?;
+    let claims: Claims = serde_json::from_slice(&payload).ok()?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    if claims.exp < now {
+        return None;
+    }
+
+    Some(claims)
+}
+
+fn get_bearer_token(request: &str) -> Option<&str> {
+    request
+        .lines()
+        .find(|line| line.starts_with("Authorization:"))?
+        .trim_start_matches("Authorization:")
+        .trim()
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim())
+}
+
+fn authenticate(request: &str) -> Option<i32> {
+    let token = get_bearer_token(request)?;
+    verify_token(token).map(|claims| claims.sub)
+}
+
+// Roles & permissions
+
+fn get_role_assignment_body(request: &str) -> Result<RoleAssignment, serde_json::Error> {
+    serde_json::from_str(request.split("\r\n\r\n").last().unwrap_or_default())
+}
+
+// the union of permissions across all of a user's roles
+fn user_permissions(client: &mut Client, user_id: i32) -> Result<Vec<String>, PostgresError> {
+    let rows = client.query(
+        "
+        SELECT DISTINCT p.name
+        FROM permissions p
+        JOIN role_permissions rp ON rp.permission_id = p.id
+        JOIN user_roles ur ON ur.role_id = rp.role_id
+        WHERE ur.user_id = $1
+    ",
+        &[&user_id],
+    )?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+// whether a user has a given permission
+fn user_has_permission(client: &mut Client, user_id: i32, permission: &str) -> bool {
+    user_permissions(client, user_id)
+        .map(|permissions| permissions.iter().any(|p| p == permission))
+        .unwrap_or(false)
+}
+
 // Controllers
 
-fn handle_get_all_users_request() -> (String, String) {
-    match Client::connect(DB_URL, NoTls) {
+fn handle_get_all_users_request(user_id: i32, pool: &DbPool, query: &ListQuery) -> (String, String) {
+    if query.limit.is_some_and(|limit| limit < 0) {
+        return bad_request("limit must not be negative");
+    }
+    if query.offset.is_some_and(|offset| offset < 0) {
+        return bad_request("offset must not be negative");
+    }
+
+    match pool.get() {
         Ok(mut client) => {
+            if !user_has_permission(&mut *client, user_id, "VIEW_USER") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
+            let mut sql = "SELECT * FROM users".to_string();
+            let mut params: Vec<&(dyn ToSql + Sync)> = vec![];
+            let mut conditions = vec![];
+
+            if let Some(email) = &query.email {
+                params.push(email);
+                conditions.push(format!("email = ${}", params.len()));
+            }
+            if let Some(name) = &query.name {
+                params.push(name);
+                conditions.push(format!("name = ${}", params.len()));
+            }
+            if !conditions.is_empty() {
+                sql.push_str(" WHERE ");
+                sql.push_str(&conditions.join(" AND "));
+            }
+
+            if let Some(limit) = &query.limit {
+                params.push(limit);
+                sql.push_str(&format!(" LIMIT ${}", params.len()));
+            }
+            if let Some(offset) = &query.offset {
+                params.push(offset);
+                sql.push_str(&format!(" OFFSET ${}", params.len()));
+            }
+
             let mut users = vec![];
-            for row in client.query("SELECT * FROM users", &[]).unwrap() {
-                users.push(User {
+            for row in client.query(sql.as_str(), &params).unwrap() {
+                users.push(UserInformation {
                     id: row.get(0),
                     name: row.get(1),
                     email: row.get(2),
-                    password: row.get(3),
+                    attributes: row.get(4),
                 });
             }
             (
@@ -138,19 +571,25 @@ fn handle_get_all_users_request() -> (String, String) {
     }
 }
 
-fn handle_get_request(request: &str) -> (String, String) {
-    match (
-        get_id(&request).parse::<i32>().unwrap(),
-        Client::connect(DB_URL, NoTls).map_err(PostgresError::from),
-    ) {
+fn handle_get_request(request: &str, user_id: i32, pool: &DbPool) -> (String, String) {
+    let id = match parse_id(&request) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match (id, pool.get()) {
         (id, Ok(mut client)) => {
+            if id != user_id && !user_has_permission(&mut *client, user_id, "VIEW_USER") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
             match client.query_one("SELECT * FROM users WHERE id = $1", &[&id]) {
                 Ok(row) => {
-                    let user = User {
+                    let user = UserInformation {
                         id: row.get(0),
                         name: row.get(1),
                         email: row.get(2),
-                        password: row.get(3),
+                        attributes: row.get(4),
                     };
                     (
                         OK_RESPONSE.to_string(),
@@ -164,24 +603,71 @@ fn handle_get_request(request: &str) -> (String, String) {
     }
 }
 
-fn handle_post_request(request: &str) -> (String, String) {
-    match (
-        get_user_request_body(&request),
-        Client::connect(DB_URL, NoTls),
-    ) {
+fn handle_login_request(request: &str, pool: &DbPool) -> (String, String) {
+    let body = request.split("\r\n\r\n").last().unwrap_or_default();
+    match (serde_json::from_str::<LoginRequest>(body), pool.get()) {
+        (Ok(login), Ok(mut client)) => {
+            match client.query_one(
+                "SELECT id, password FROM users WHERE email = $1",
+                &[&login.email],
+            ) {
+                Ok(row) => {
+                    let id: i32 = row.get(0);
+                    let password_hash: String = row.get(1);
+
+                    if bcrypt::verify(&login.password, &password_hash).unwrap_or(false) {
+                        let token = create_token(id);
+                        (
+                            OK_RESPONSE.to_string(),
+                            serde_json::to_string(&serde_json::json!({ "token": token })).unwrap(),
+                        )
+                    } else {
+                        (UNAUTHORIZED.to_string(), "Invalid credentials".to_string())
+                    }
+                }
+                Err(_) => (UNAUTHORIZED.to_string(), "Invalid credentials".to_string()),
+            }
+        }
+        _ => (
+            INTERNAL_SERVER_ERROR.to_string(),
+            "Error logging in".to_string(),
+        ),
+    }
+}
+
+fn handle_post_request(request: &str, user_id: i32, pool: &DbPool) -> (String, String) {
+    match (get_user_request_body(&request), pool.get()) {
         (Ok(user), Ok(mut client)) => {
+            if let Err(msg) = user.check() {
+                return bad_request(&msg);
+            }
+
+            if !user_has_permission(&mut *client, user_id, "CREATE_USER") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
+            // Hash the password so creation never persists plaintext
+            let password = bcrypt::hash(&user.password, bcrypt::DEFAULT_COST).unwrap();
+
             // Insert the user
-            client
-                .execute(
-                    "INSERT INTO users (name, email, password) VALUES ($1, $2, $3)",
-                    &[&user.name, &user.email, &user.password],
+            let id: i32 = client
+                .query_one(
+                    "INSERT INTO users (name, email, password, attributes) VALUES ($1, $2, $3, $4) RETURNING id",
+                    &[&user.name, &user.email, &password, &user.attributes],
                 )
-                .unwrap();
+                .unwrap()
+                .get(0);
 
             // Return the response
+            let created = UserInformation {
+                id: Some(id),
+                name: user.name,
+                email: user.email,
+                attributes: user.attributes,
+            };
             (
                 OK_RESPONSE.to_string(),
-                serde_json::to_string(&user).unwrap(),
+                serde_json::to_string(&created).unwrap(),
             )
         }
         _ => (
@@ -190,13 +676,21 @@ fn handle_post_request(request: &str) -> (String, String) {
         ),
     }
 }
-fn handle_put_request(request: &str) -> (String, String) {
-    match (
-        get_id(&request).parse::<i32>().unwrap(),
-        get_user_request_body(&request),
-        Client::connect(DB_URL, NoTls),
-    ) {
+fn handle_put_request(request: &str, user_id: i32, pool: &DbPool) -> (String, String) {
+    let id = match parse_id(&request) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match (id, get_user_request_body(&request), pool.get()) {
         (id, Ok(user), Ok(mut client)) => {
+            if let Err(msg) = user.check() {
+                return bad_request(&msg);
+            }
+
+            if id != user_id && !user_has_permission(&mut *client, user_id, "UPDATE_USER") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
 
             let mut password = user.password.clone();
 
@@ -209,8 +703,8 @@ fn handle_put_request(request: &str) -> (String, String) {
             // Update the user
             client
                 .execute(
-                    "UPDATE users SET name = $1, email = $2, password = $3 WHERE id = $4",
-                    &[&user.name, &user.email, &password, &id],
+                    "UPDATE users SET name = $1, email = $2, password = $3, attributes = $4 WHERE id = $5",
+                    &[&user.name, &user.email, &password, &user.attributes, &id],
                 )
                 .unwrap();
 
@@ -226,12 +720,18 @@ fn handle_put_request(request: &str) -> (String, String) {
         ),
     }
 }
-fn handle_delete_request(request: &str) -> (String, String) {
-    match (
-        get_id(&request).parse::<i32>().unwrap(),
-        Client::connect(DB_URL, NoTls),
-    ) {
+fn handle_delete_request(request: &str, user_id: i32, pool: &DbPool) -> (String, String) {
+    let id = match parse_id(&request) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match (id, pool.get()) {
         (id, Ok(mut client)) => {
+            if !user_has_permission(&mut *client, user_id, "DELETE_USER") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
             // Delete the user
             let rows_affected = client
                 .execute("DELETE FROM users WHERE id = $1", &[&id])
@@ -250,3 +750,93 @@ fn handle_delete_request(request: &str) -> (String, String) {
         ),
     }
 }
+
+fn handle_assign_role_request(request: &str, caller_id: i32, pool: &DbPool) -> (String, String) {
+    let user_id = match parse_id(&request) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match (get_role_assignment_body(&request), pool.get()) {
+        (Ok(assignment), Ok(mut client)) => {
+            if !user_has_permission(&mut *client, caller_id, "MANAGE_ROLES") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
+            client
+                .execute(
+                    "INSERT INTO user_roles (user_id, role_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                    &[&user_id, &assignment.role_id],
+                )
+                .unwrap();
+            (OK_RESPONSE.to_string(), "Role assigned".to_string())
+        }
+        _ => (
+            INTERNAL_SERVER_ERROR.to_string(),
+            "Error assigning role".to_string(),
+        ),
+    }
+}
+
+fn handle_remove_role_request(request: &str, caller_id: i32, pool: &DbPool) -> (String, String) {
+    let user_id = match parse_id(&request) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match (get_role_assignment_body(&request), pool.get()) {
+        (Ok(assignment), Ok(mut client)) => {
+            if !user_has_permission(&mut *client, caller_id, "MANAGE_ROLES") {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
+            client
+                .execute(
+                    "DELETE FROM user_roles WHERE user_id = $1 AND role_id = $2",
+                    &[&user_id, &assignment.role_id],
+                )
+                .unwrap();
+            (OK_RESPONSE.to_string(), "Role removed".to_string())
+        }
+        _ => (
+            INTERNAL_SERVER_ERROR.to_string(),
+            "Error removing role".to_string(),
+        ),
+    }
+}
+
+fn handle_get_user_permissions_request(
+    request: &str,
+    caller_id: i32,
+    pool: &DbPool,
+) -> (String, String) {
+    let user_id = match parse_id(&request) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match pool.get() {
+        Ok(mut client) => {
+            if user_id != caller_id
+                && !user_has_permission(&mut *client, caller_id, "MANAGE_ROLES")
+            {
+                return (FORBIDDEN.to_string(), "Forbidden".to_string());
+            }
+
+            match user_permissions(&mut *client, user_id) {
+                Ok(permissions) => (
+                    OK_RESPONSE.to_string(),
+                    serde_json::to_string(&permissions).unwrap(),
+                ),
+                Err(_) => (
+                    INTERNAL_SERVER_ERROR.to_string(),
+                    "Error fetching permissions".to_string(),
+                ),
+            }
+        }
+        _ => (
+            INTERNAL_SERVER_ERROR.to_string(),
+            "Error fetching permissions".to_string(),
+        ),
+    }
+}